@@ -1,15 +1,22 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::fmt::Formatter;
-use std::fs::{File};
+use std::fmt::{Formatter, Write as _};
+use std::fs::{self, File};
 use std::path::Path;
 use std::str::{FromStr};
 
 use calamine::{DataType, open_workbook, Reader, Xlsx};
-use xlsxwriter::{Workbook};
+use xlsxwriter::{Format, Workbook};
 use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
 use clap::Parser;
+use csv::{ReaderBuilder, StringRecord};
+use encoding_rs::WINDOWS_1252;
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use rayon::prelude::*;
 use regex::Regex;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive, Zero};
+use serde::Deserialize;
 
 const INVOICE_EMISSION_INDEX: i8 = 0;
 const INVOICE_NUMBER_INDEX: i8 = 1;
@@ -19,9 +26,9 @@ const INVOICE_VALUE_INDEX: i8 = 10;
 
 #[derive(Parser)]
 struct Args {
-    /// Caminho da planilha de origem
-    #[clap(short, long)]
-    input: String,
+    /// Caminho da(s) planilha(s) de origem
+    #[clap(short, long, multiple_values = true)]
+    input: Vec<String>,
 
     /// Planilha de entrada
     #[clap(short, long, default_value = "VENDAS")]
@@ -30,6 +37,132 @@ struct Args {
     /// Caminho onde a planilha resultado sera salva
     #[clap(short, long)]
     output: String,
+
+    /// Formato do arquivo de entrada, detectado pela extensão quando omitido
+    #[clap(long, arg_enum)]
+    format: Option<InputFormat>,
+
+    /// Separador de campos do CSV de entrada
+    #[clap(long, default_value = ";")]
+    delimiter: char,
+
+    /// Quantidade de linhas de cabeçalho a pular no CSV de entrada
+    #[clap(long, default_value = "1")]
+    skip_rows: usize,
+
+    /// Caminho para um arquivo TOML com mapeamento de colunas e regras de comissão
+    #[clap(long)]
+    config: Option<String>,
+
+    /// Formato de exportação da saída, detectado pela extensão quando omitido
+    #[clap(long, arg_enum)]
+    export: Option<ExportFormat>,
+}
+
+#[derive(Clone, clap::ArgEnum)]
+enum InputFormat {
+    Xlsx,
+    Csv,
+    Pdf,
+}
+
+#[derive(Clone, clap::ArgEnum)]
+enum ExportFormat {
+    Xlsx,
+    Qif,
+    Ledger,
+    Html,
+}
+
+/// Uma coluna da planilha de origem, referenciada pelo índice (0-based) ou
+/// pelo texto do cabeçalho.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum ColumnRef {
+    Index(usize),
+    Header(String),
+}
+
+// Entrada em PDF não tem índice de coluna fixo, então todo `columns.*` do
+// config precisa usar `Header("...")`, por exemplo:
+//   [columns]
+//   emission = "Emissão"
+//   number = "Número"
+//   client = "Cliente"
+//   payment_interval = "Parcelamento"
+//   value = "Valor"
+#[derive(Deserialize)]
+#[serde(default)]
+struct ColumnsConfig {
+    emission: ColumnRef,
+    number: ColumnRef,
+    client: ColumnRef,
+    payment_interval: ColumnRef,
+    value: ColumnRef,
+}
+
+impl Default for ColumnsConfig {
+    fn default() -> Self {
+        ColumnsConfig {
+            emission: ColumnRef::Index(INVOICE_EMISSION_INDEX as usize),
+            number: ColumnRef::Index(INVOICE_NUMBER_INDEX as usize),
+            client: ColumnRef::Index(CLIENT_NAME_INDEX as usize),
+            payment_interval: ColumnRef::Index(PAYMENT_INTERVAL_INDEX as usize),
+            value: ColumnRef::Index(INVOICE_VALUE_INDEX as usize),
+        }
+    }
+}
+
+/// Uma regra de comissão aplicada quando `pattern` casa com a célula de
+/// intervalo de pagamento; a porcentagem é lida do grupo `capture_group`.
+#[derive(Deserialize)]
+struct CommissionOverride {
+    pattern: String,
+    capture_group: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+struct CommissionConfig {
+    columns: ColumnsConfig,
+    default_commission_percentage: f64,
+    no_installment_offset_days: i64,
+    no_installment_pattern: String,
+    commission_overrides: Vec<CommissionOverride>,
+}
+
+impl Default for CommissionConfig {
+    fn default() -> Self {
+        CommissionConfig {
+            columns: ColumnsConfig::default(),
+            default_commission_percentage: 7.,
+            no_installment_offset_days: 30,
+            no_installment_pattern: String::from("ANTECIPADO / A VISTA [2]"),
+            commission_overrides: vec![CommissionOverride { pattern: String::from(r"(\d)%"), capture_group: 1 }],
+        }
+    }
+}
+
+fn load_commission_config(args: &Args) -> CommissionConfig {
+    match &args.config {
+        Some(path) => {
+            let contents = std::fs::read_to_string(path)
+                .unwrap_or_else(|cause| panic!("could not read config {}: {}", path, cause));
+
+            toml::from_str(&contents)
+                .unwrap_or_else(|cause| panic!("invalid config {}: {}", path, cause))
+        }
+        None => CommissionConfig::default(),
+    }
+}
+
+fn resolve_column(column: &ColumnRef, header: &[String]) -> usize {
+    match column {
+        ColumnRef::Index(index) => *index,
+        ColumnRef::Header(name) => header.iter()
+            .position(|cell| cell.trim().eq_ignore_ascii_case(name.trim()))
+            .unwrap_or_else(|| panic!("coluna '{}' não encontrada no cabeçalho", name)),
+    }
 }
 
 struct Invoice {
@@ -37,7 +170,7 @@ struct Invoice {
     number: f64,
     client: String,
     payment_interval: String,
-    value: f64,
+    value: Decimal,
 }
 
 impl fmt::Display for Invoice {
@@ -53,8 +186,8 @@ struct CommissionedInvoice {
     emission_date: NaiveDate,
     number: f64,
     client: String,
-    installment_value: f64,
-    commission_value: f64,
+    installment_value: Decimal,
+    commission_value: Decimal,
 }
 
 impl fmt::Display for CommissionedInvoice {
@@ -68,8 +201,9 @@ impl fmt::Display for CommissionedInvoice {
 
 fn main() {
     let args = Args::parse();
-    let invoices: Vec<Invoice> = get_invoices(&args);
-    let commissions_by_month = get_commissions_by_month(invoices);
+    let config = load_commission_config(&args);
+    let invoices: Vec<Invoice> = get_invoices(&args, &config);
+    let commissions_by_month = get_commissions_by_month(invoices, &config);
     let mut ordered_months = commissions_by_month.keys().cloned()
         .collect::<Vec<String>>();
 
@@ -82,7 +216,12 @@ fn main() {
         a_date.cmp(&b_date)
     });
 
-    create_commission_sheets(&args.output, ordered_months, &commissions_by_month);
+    match resolve_export_format(&args) {
+        ExportFormat::Xlsx => create_commission_sheets(&args.output, ordered_months, &commissions_by_month),
+        ExportFormat::Qif => write_qif_export(&args.output, &ordered_months, &commissions_by_month),
+        ExportFormat::Ledger => write_ledger_export(&args.output, &ordered_months, &commissions_by_month),
+        ExportFormat::Html => write_html_report(&args.output, &ordered_months, &commissions_by_month),
+    }
 
     // for (month, commissions) in commissions_by_month {
     //     println!("\n\n{}", month);
@@ -93,13 +232,62 @@ fn main() {
     // }
 }
 
-fn get_invoices(args: &Args) -> Vec<Invoice> {
+fn get_invoices(args: &Args, config: &CommissionConfig) -> Vec<Invoice> {
+    args.input.par_iter()
+        .flat_map(|path| get_invoices_from_path(path, args, config).into_par_iter())
+        .collect()
+}
+
+fn get_invoices_from_path(path: &str, args: &Args, config: &CommissionConfig) -> Vec<Invoice> {
+    match resolve_input_format(path, args) {
+        InputFormat::Xlsx => get_invoices_from_xlsx(path, args, config),
+        InputFormat::Csv => get_invoices_from_csv(path, args, config),
+        InputFormat::Pdf => get_invoices_from_pdf(path, config),
+    }
+}
+
+fn resolve_input_format(path: &str, args: &Args) -> InputFormat {
+    if let Some(format) = &args.format {
+        return format.clone();
+    }
+
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => InputFormat::Csv,
+        Some(ext) if ext.eq_ignore_ascii_case("pdf") => InputFormat::Pdf,
+        _ => InputFormat::Xlsx,
+    }
+}
+
+fn resolve_export_format(args: &Args) -> ExportFormat {
+    if let Some(format) = &args.export {
+        return format.clone();
+    }
+
+    match Path::new(&args.output).extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("qif") => ExportFormat::Qif,
+        Some(ext) if ext.eq_ignore_ascii_case("ledger") => ExportFormat::Ledger,
+        Some(ext) if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") => ExportFormat::Html,
+        _ => ExportFormat::Xlsx,
+    }
+}
+
+fn get_invoices_from_xlsx(path: &str, args: &Args, config: &CommissionConfig) -> Vec<Invoice> {
     let mut invoices: Vec<Invoice> = Vec::new();
-    let mut workbook: Xlsx<_> = open_workbook(&args.input).unwrap();
+    let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
 
     if let Some(Ok(range)) = workbook.worksheet_range(&args.sheet) {
+        let header: Vec<String> = range.rows().next()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+            .unwrap_or_default();
+
+        let emission_index = resolve_column(&config.columns.emission, &header);
+        let number_index = resolve_column(&config.columns.number, &header);
+        let client_index = resolve_column(&config.columns.client, &header);
+        let payment_interval_index = resolve_column(&config.columns.payment_interval, &header);
+        let value_index = resolve_column(&config.columns.value, &header);
+
         for row in range.rows().skip(1) {
-            let emission_date = match &row[INVOICE_EMISSION_INDEX as usize] {
+            let emission_date = match &row[emission_index] {
                 DataType::DateTime(f) => {
                     let unix_secs = (f - 25569.) * 86400.;
                     let secs = unix_secs.trunc() as i64;
@@ -117,10 +305,12 @@ fn get_invoices(args: &Args) -> Vec<Invoice> {
             if emission_date.is_some() {
                 invoices.push(Invoice {
                     emission_date: emission_date.unwrap(),
-                    number: row[INVOICE_NUMBER_INDEX as usize].get_float().unwrap_or(f64::from(0)),
-                    client: String::from(row[CLIENT_NAME_INDEX as usize].get_string().unwrap_or("")),
-                    payment_interval: String::from(row[PAYMENT_INTERVAL_INDEX as usize].get_string().unwrap_or("")),
-                    value: row[INVOICE_VALUE_INDEX as usize].get_float().unwrap_or(f64::from(0)),
+                    number: row[number_index].get_float().unwrap_or(f64::from(0)),
+                    client: String::from(row[client_index].get_string().unwrap_or("")),
+                    payment_interval: String::from(row[payment_interval_index].get_string().unwrap_or("")),
+                    value: row[value_index].get_float()
+                        .and_then(Decimal::from_f64)
+                        .unwrap_or(Decimal::zero()),
                 });
             }
         }
@@ -129,40 +319,263 @@ fn get_invoices(args: &Args) -> Vec<Invoice> {
     invoices
 }
 
-fn get_commissions_by_month(invoices: Vec<Invoice>) -> HashMap<String, Vec<CommissionedInvoice>> {
+fn get_invoices_from_csv(path: &str, args: &Args, config: &CommissionConfig) -> Vec<Invoice> {
+    let mut invoices: Vec<Invoice> = Vec::new();
+    let file = File::open(path).unwrap();
+    let transcoded = DecodeReaderBytesBuilder::new()
+        .encoding(Some(WINDOWS_1252))
+        .build(file);
+    let mut reader = ReaderBuilder::new()
+        .delimiter(args.delimiter as u8)
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(transcoded);
+
+    let records: Vec<StringRecord> = reader.records().filter_map(Result::ok).collect();
+    let header: Vec<String> = records.get(args.skip_rows.saturating_sub(1))
+        .map(|record| record.iter().map(String::from).collect())
+        .unwrap_or_default();
+
+    let emission_index = resolve_column(&config.columns.emission, &header);
+    let number_index = resolve_column(&config.columns.number, &header);
+    let client_index = resolve_column(&config.columns.client, &header);
+    let payment_interval_index = resolve_column(&config.columns.payment_interval, &header);
+    let value_index = resolve_column(&config.columns.value, &header);
+
+    for record in records.into_iter().skip(args.skip_rows) {
+        let emission_date = record.get(emission_index)
+            .and_then(|s| NaiveDate::parse_from_str(s.trim(), "%m/%d/%Y").ok());
+
+        if let Some(emission_date) = emission_date {
+            invoices.push(Invoice {
+                emission_date,
+                number: record.get(number_index)
+                    .and_then(|s| f64::from_str(s.trim()).ok()).unwrap_or(0.),
+                client: record.get(client_index).unwrap_or("").trim().to_string(),
+                payment_interval: record.get(payment_interval_index).unwrap_or("").trim().to_string(),
+                value: record.get(value_index)
+                    .map(parse_brl_decimal).unwrap_or(Decimal::zero()),
+            });
+        }
+    }
+
+    invoices
+}
+
+fn parse_brl_decimal(raw: &str) -> Decimal {
+    let normalized = raw.trim().replace('.', "").replace(',', ".");
+    Decimal::from_str(&normalized).unwrap_or(Decimal::zero())
+}
+
+const PDF_COLUMN_X_ERROR_MARGIN: f64 = 5.;
+const PDF_ROW_Y_TOLERANCE: f64 = 2.;
+
+struct PdfTextFragment {
+    page: u32,
+    x: f64,
+    y: f64,
+    text: String,
+}
+
+struct PdfTextCollector {
+    fragments: Vec<PdfTextFragment>,
+    current_page: u32,
+}
+
+impl pdf_extract::OutputDev for PdfTextCollector {
+    fn begin_page(&mut self, page_num: u32, _media_box: &pdf_extract::MediaBox, _art_box: Option<(f64, f64, f64, f64)>) -> Result<(), pdf_extract::OutputError> {
+        self.current_page = page_num;
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+
+    fn output_character(&mut self, trm: &pdf_extract::Transform, _width: f64, _spacing: f64, _font_size: f64, text: &str) -> Result<(), pdf_extract::OutputError> {
+        if !text.trim().is_empty() {
+            self.fragments.push(PdfTextFragment { page: self.current_page, x: trm.m31, y: trm.m32, text: text.to_string() });
+        }
+
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+}
+
+fn extract_pdf_text_fragments(path: &str) -> Vec<PdfTextFragment> {
+    let document = pdf_extract::Document::load(path).unwrap();
+    let mut collector = PdfTextCollector { fragments: Vec::new(), current_page: 0 };
+    pdf_extract::output_doc(&document, &mut collector).unwrap();
+
+    collector.fragments
+}
+
+// PDF page coordinates reset per page, so rows are grouped by (page, y) —
+// never just y — or a row on page 2 could merge with one on page 1.
+fn group_pdf_rows(mut fragments: Vec<PdfTextFragment>) -> Vec<Vec<PdfTextFragment>> {
+    fragments.sort_by(|a, b| a.page.cmp(&b.page).then(b.y.partial_cmp(&a.y).unwrap()));
+
+    let mut rows: Vec<Vec<PdfTextFragment>> = Vec::new();
+    for fragment in fragments {
+        match rows.last_mut() {
+            Some(row) if row[0].page == fragment.page
+                && (row[0].y - fragment.y).abs() <= PDF_ROW_Y_TOLERANCE => row.push(fragment),
+            _ => rows.push(vec![fragment]),
+        }
+    }
+
+    for row in &mut rows {
+        row.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
+    }
+
+    rows
+}
+
+fn pdf_column_label(column: &ColumnRef) -> &str {
+    match column {
+        ColumnRef::Header(label) => label,
+        ColumnRef::Index(_) => panic!("PDF input requires header-name columns in the config, not numeric indices"),
+    }
+}
+
+fn pdf_column_labels(config: &CommissionConfig) -> [(&'static str, &str); 5] {
+    [
+        ("emission", pdf_column_label(&config.columns.emission)),
+        ("number", pdf_column_label(&config.columns.number)),
+        ("client", pdf_column_label(&config.columns.client)),
+        ("payment_interval", pdf_column_label(&config.columns.payment_interval)),
+        ("value", pdf_column_label(&config.columns.value)),
+    ]
+}
+
+fn is_pdf_header_row(row: &[PdfTextFragment], labels: &[(&str, &str); 5]) -> bool {
+    labels.iter().all(|(_, label)| {
+        row.iter().any(|fragment| fragment.text.trim().eq_ignore_ascii_case(label))
+    })
+}
+
+fn locate_pdf_column_positions(header_row: &[PdfTextFragment], labels: &[(&str, &str); 5]) -> Vec<(&'static str, f64)> {
+    labels.iter().filter_map(|(field, label)| {
+        header_row.iter()
+            .find(|fragment| fragment.text.trim().eq_ignore_ascii_case(label))
+            .map(|fragment| (*field, fragment.x))
+    }).collect()
+}
+
+fn nearest_pdf_column<'a>(x: f64, column_positions: &'a [(&'static str, f64)]) -> Option<&'a str> {
+    column_positions.iter()
+        .min_by(|(_, a), (_, b)| (a - x).abs().partial_cmp(&(b - x).abs()).unwrap())
+        .filter(|(_, position)| (position - x).abs() <= PDF_COLUMN_X_ERROR_MARGIN)
+        .map(|(field, _)| *field)
+}
+
+fn get_invoices_from_pdf(path: &str, config: &CommissionConfig) -> Vec<Invoice> {
+    let rows = group_pdf_rows(extract_pdf_text_fragments(path));
+    let labels = pdf_column_labels(config);
+
+    let header_row_index = rows.iter().position(|row| is_pdf_header_row(row, &labels))
+        .unwrap_or_else(|| panic!("could not find a header row matching the configured column labels"));
+    let column_positions = locate_pdf_column_positions(&rows[header_row_index], &labels);
+
+    let date_regex = Regex::new(r"\d{1,2}/\d{1,2}/\d{4}").unwrap();
+    let numeric_regex = Regex::new(r"-?\d[\d.,]*").unwrap();
+
+    let mut invoices = Vec::new();
+
+    for (index, row) in rows.iter().enumerate() {
+        if index == header_row_index {
+            continue;
+        }
+
+        let mut cells: HashMap<&str, String> = HashMap::new();
+        for fragment in row {
+            if let Some(field) = nearest_pdf_column(fragment.x, &column_positions) {
+                cells.entry(field).or_insert_with(String::new).push_str(&fragment.text);
+            }
+        }
+
+        let emission_date = cells.get("emission")
+            .and_then(|text| date_regex.find(text))
+            .and_then(|m| NaiveDate::parse_from_str(m.as_str(), "%m/%d/%Y").ok());
+
+        if let Some(emission_date) = emission_date {
+            invoices.push(Invoice {
+                emission_date,
+                number: cells.get("number")
+                    .and_then(|text| f64::from_str(text.trim()).ok()).unwrap_or(0.),
+                client: cells.get("client").cloned().unwrap_or_default(),
+                payment_interval: cells.get("payment_interval").cloned().unwrap_or_default(),
+                value: cells.get("value")
+                    .and_then(|text| numeric_regex.find(text))
+                    .map(|m| parse_brl_decimal(m.as_str()))
+                    .unwrap_or(Decimal::zero()),
+            });
+        }
+    }
+
+    invoices
+}
+
+fn get_commissions_by_month(invoices: Vec<Invoice>, config: &CommissionConfig) -> HashMap<String, Vec<CommissionedInvoice>> {
     let mut invoices_by_month = HashMap::new();
     let installments_regex: Regex = Regex::new(r"^((\d{2,3}/?)+)").unwrap();
-    let special_commission_regex: Regex = Regex::new(r"(\d)%").unwrap();
+    let default_commission = Decimal::from_f64(config.default_commission_percentage).unwrap_or(Decimal::from(7));
+    let commission_overrides = compile_commission_overrides(config);
 
     for invoice in invoices {
-        if invoice.payment_interval.trim() == "ANTECIPADO / A VISTA [2]"
+        if let Some(rule) = parse_rrule(&invoice.payment_interval) {
+            let occurrences = expand_rrule(&rule, invoice.emission_date);
+            let commission = resolve_commission_percentage(&invoice.payment_interval, &commission_overrides, default_commission);
+            let installment_values = split_into_installments(invoice.value, occurrences.len());
+
+            for (occurrence, installment_value) in occurrences.iter().zip(installment_values) {
+                let installment_month = occurrence.format("%B %Y").to_string();
+
+                invoices_by_month.entry(installment_month).or_insert(Vec::new())
+                    .push(CommissionedInvoice {
+                        emission_date: invoice.emission_date,
+                        number: invoice.number,
+                        client: invoice.client.clone(),
+                        installment_value,
+                        commission_value: commission_value(installment_value, commission),
+                    })
+            }
+        } else if invoice.payment_interval.trim() == config.no_installment_pattern.trim()
             || !installments_regex.is_match(&invoice.payment_interval) {
-            let next_month = (invoice.emission_date + Duration::days(30))
+            let next_month = (invoice.emission_date + Duration::days(config.no_installment_offset_days))
                 .format("%B %Y").to_string();
-            let commissioned_invoices = invoices_by_month
-                .entry(next_month).or_insert(Vec::new());
-
-            commissioned_invoices.push(CommissionedInvoice {
-                emission_date: invoice.emission_date,
-                number: invoice.number,
-                client: invoice.client,
-                installment_value: invoice.value,
-                commission_value: (f64::from(7) * invoice.value) / f64::from(100),
-            });
+            let commission = resolve_commission_percentage(&invoice.payment_interval, &commission_overrides, default_commission);
+
+            invoices_by_month.entry(next_month).or_insert(Vec::new())
+                .push(CommissionedInvoice {
+                    emission_date: invoice.emission_date,
+                    number: invoice.number,
+                    client: invoice.client,
+                    installment_value: invoice.value,
+                    commission_value: commission_value(invoice.value, commission),
+                });
         } else {
             let intervals: Vec<&str> = installments_regex
                 .captures(&invoice.payment_interval).unwrap().get(1)
                 .map(|m| m.as_str().split("/")).unwrap().collect::<Vec<&str>>();
-            let commission: f64 = if special_commission_regex.is_match(&invoice.payment_interval) {
-                special_commission_regex.captures(&invoice.payment_interval).unwrap().get(1)
-                    .map(|m| f64::from_str(m.as_str())).unwrap().unwrap()
-            } else { 7. };
+            let commission = resolve_commission_percentage(&invoice.payment_interval, &commission_overrides, default_commission);
+            let installment_values = split_into_installments(invoice.value, intervals.len());
 
-            for interval in &intervals {
+            for (interval, installment_value) in intervals.iter().zip(installment_values) {
                 let days = i16::from_str(&interval).unwrap();
                 let installment_month = (invoice.emission_date + Duration::days(days as i64))
                     .format("%B %Y").to_string();
-                let installment_value = invoice.value / intervals.len() as f64;
 
                 invoices_by_month.entry(installment_month).or_insert(Vec::new())
                     .push(CommissionedInvoice {
@@ -170,7 +583,7 @@ fn get_commissions_by_month(invoices: Vec<Invoice>) -> HashMap<String, Vec<Commi
                         number: invoice.number,
                         client: invoice.client.clone(),
                         installment_value,
-                        commission_value: (commission * installment_value) / f64::from(100),
+                        commission_value: commission_value(installment_value, commission),
                     })
             }
         }
@@ -179,10 +592,180 @@ fn get_commissions_by_month(invoices: Vec<Invoice>) -> HashMap<String, Vec<Commi
     invoices_by_month
 }
 
+fn compile_commission_overrides(config: &CommissionConfig) -> Vec<(Regex, usize)> {
+    config.commission_overrides.iter()
+        .filter_map(|rule| Regex::new(&rule.pattern).ok().map(|regex| (regex, rule.capture_group)))
+        .collect()
+}
+
+fn resolve_commission_percentage(payment_interval: &str, overrides: &[(Regex, usize)], default_commission: Decimal) -> Decimal {
+    for (regex, capture_group) in overrides {
+        let matched_percentage = regex.captures(payment_interval)
+            .and_then(|captures| captures.get(*capture_group))
+            .and_then(|m| Decimal::from_str(m.as_str()).ok());
+
+        if let Some(percentage) = matched_percentage {
+            return percentage;
+        }
+    }
+
+    default_commission
+}
+
+fn commission_value(value: Decimal, commission_percentage: Decimal) -> Decimal {
+    ((commission_percentage * value) / Decimal::from(100)).round_dp(2)
+}
+
+enum RRuleFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+struct RRule {
+    frequency: RRuleFrequency,
+    interval: i64,
+    count: Option<u32>,
+    until: Option<NaiveDate>,
+}
+
+fn parse_rrule(payment_interval: &str) -> Option<RRule> {
+    let spec = payment_interval.trim().strip_prefix("RRULE:")?;
+
+    let mut frequency = None;
+    let mut interval = 1i64;
+    let mut count = None;
+    let mut until = None;
+
+    for part in spec.split(';') {
+        let mut key_value = part.splitn(2, '=');
+        let key = key_value.next()?.trim();
+        let value = key_value.next()?.trim();
+
+        match key {
+            "FREQ" => frequency = match value {
+                "DAILY" => Some(RRuleFrequency::Daily),
+                "WEEKLY" => Some(RRuleFrequency::Weekly),
+                "MONTHLY" => Some(RRuleFrequency::Monthly),
+                _ => None,
+            },
+            "INTERVAL" => {
+                interval = value.parse().ok()?;
+                if interval <= 0 {
+                    return None;
+                }
+            }
+            "COUNT" => count = value.parse().ok(),
+            "UNTIL" => until = NaiveDate::parse_from_str(value, "%Y%m%d").ok(),
+            _ => {}
+        }
+    }
+
+    Some(RRule { frequency: frequency?, interval, count, until })
+}
+
+fn expand_rrule(rule: &RRule, emission_date: NaiveDate) -> Vec<NaiveDate> {
+    let mut occurrences = Vec::new();
+    let mut current = emission_date;
+
+    loop {
+        current = advance_date(current, &rule.frequency, rule.interval);
+
+        if let Some(until) = rule.until {
+            if current > until {
+                break;
+            }
+        }
+
+        occurrences.push(current);
+
+        match rule.count {
+            Some(count) if occurrences.len() as u32 >= count => break,
+            Some(_) => {}
+            None if rule.until.is_none() => break,
+            None => {}
+        }
+    }
+
+    // An UNTIL earlier than the first advanced occurrence leaves no occurrence
+    // (COUNT never does: the first one is kept before COUNT is checked); fall
+    // back to emission_date so the invoice is not silently dropped.
+    if occurrences.is_empty() {
+        occurrences.push(emission_date);
+    }
+
+    occurrences
+}
+
+fn advance_date(date: NaiveDate, frequency: &RRuleFrequency, interval: i64) -> NaiveDate {
+    match frequency {
+        RRuleFrequency::Daily => date + Duration::days(interval),
+        RRuleFrequency::Weekly => date + Duration::weeks(interval),
+        RRuleFrequency::Monthly => add_months(date, interval),
+    }
+}
+
+// Clamps the day to the last day of the target month (Jan 31 + 1 month -> Feb 28/29).
+fn add_months(date: NaiveDate, months: i64) -> NaiveDate {
+    let total_months = date.year() as i64 * 12 + (date.month() as i64 - 1) + months;
+    let year = total_months.div_euclid(12) as i32;
+    let month = (total_months.rem_euclid(12) + 1) as u32;
+    let day = date.day().min(last_day_of_month(year, month));
+
+    NaiveDate::from_ymd(year, month, day)
+}
+
+fn last_day_of_month(year: i32, month: u32) -> u32 {
+    let next_month_first_day = if month == 12 {
+        NaiveDate::from_ymd(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd(year, month + 1, 1)
+    };
+
+    (next_month_first_day - Duration::days(1)).day()
+}
+
+// Leftover cents from rounding go on the last installment so the sum matches `value` exactly.
+fn split_into_installments(value: Decimal, count: usize) -> Vec<Decimal> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let base = (value / Decimal::from(count as u64)).round_dp(2);
+    let mut installments = vec![base; count - 1];
+    installments.push(value - base * Decimal::from((count - 1) as u64));
+    installments
+}
+
 fn create_commission_sheets(output_sheet: &str, ordered_months: Vec<String>, commissions_by_month: &HashMap<String, Vec<CommissionedInvoice>>) {
     ensure_file_is_created(&output_sheet);
 
     let workbook = Workbook::new(output_sheet);
+    let mut money_format = Format::new();
+    money_format.set_num_format("#,##0.00");
+
+    let mut resumo = workbook.add_worksheet(Some("RESUMO")).unwrap();
+    resumo.write_string(0, 0, "Mês", None);
+    resumo.write_string(0, 1, "Total Parcelas", None);
+    resumo.write_string(0, 2, "Total Comissões", None);
+
+    let mut grand_installment_total = Decimal::zero();
+    let mut grand_commission_total = Decimal::zero();
+
+    for (index, month) in ordered_months.iter().enumerate() {
+        let (installment_total, commission_total) = month_totals(commissions_by_month.get(month).unwrap());
+        grand_installment_total += installment_total;
+        grand_commission_total += commission_total;
+
+        resumo.write_string((index + 1) as u32, 0, month, None);
+        resumo.write_number((index + 1) as u32, 1, installment_total.to_f64().unwrap_or(0.), Some(&money_format));
+        resumo.write_number((index + 1) as u32, 2, commission_total.to_f64().unwrap_or(0.), Some(&money_format));
+    }
+
+    let grand_total_row = (ordered_months.len() + 1) as u32;
+    resumo.write_string(grand_total_row, 0, "Total geral", None);
+    resumo.write_number(grand_total_row, 1, grand_installment_total.to_f64().unwrap_or(0.), Some(&money_format));
+    resumo.write_number(grand_total_row, 2, grand_commission_total.to_f64().unwrap_or(0.), Some(&money_format));
 
     for month in ordered_months {
         let commissions = commissions_by_month.get(&month).unwrap();
@@ -200,8 +783,8 @@ fn create_commission_sheets(output_sheet: &str, ordered_months: Vec<String>, com
             worksheet.write_string((index + 1) as u32, 0, &commission.emission_date.format("%m/%d/%Y").to_string(), None);
             worksheet.write_string((index + 1) as u32, 1, &commission.number.to_string(), None);
             worksheet.write_string((index + 1) as u32, 2, &commission.client, None);
-            worksheet.write_string((index + 1) as u32, 3, &commission.installment_value.to_string(), None);
-            worksheet.write_string((index + 1) as u32, 4, &commission.commission_value.to_string(), None);
+            worksheet.write_number((index + 1) as u32, 3, commission.installment_value.to_f64().unwrap_or(0.), Some(&money_format));
+            worksheet.write_number((index + 1) as u32, 4, commission.commission_value.to_f64().unwrap_or(0.), Some(&money_format));
         }
     }
 
@@ -218,3 +801,92 @@ fn ensure_file_is_created(output_sheet: &str) {
         }
     }
 }
+
+fn month_totals(commissions: &[CommissionedInvoice]) -> (Decimal, Decimal) {
+    commissions.iter().fold((Decimal::zero(), Decimal::zero()), |(installments, commissions), commission| {
+        (installments + commission.installment_value, commissions + commission.commission_value)
+    })
+}
+
+const HTML_REPORT_STYLE: &str = "body{font-family:sans-serif;margin:2rem;color:#222;}\
+table{border-collapse:collapse;width:100%;margin-bottom:1.5rem;}\
+th,td{border:1px solid #ccc;padding:0.4rem 0.6rem;text-align:left;}\
+tfoot th{background:#f2f2f2;}\
+.grand-total{margin-top:2rem;font-weight:bold;}";
+
+fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn write_html_report(output_path: &str, ordered_months: &[String], commissions_by_month: &HashMap<String, Vec<CommissionedInvoice>>) {
+    ensure_file_is_created(output_path);
+
+    let mut html = String::new();
+    write!(html, "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Relatório de Comissões</title><style>{}</style></head><body>", HTML_REPORT_STYLE).unwrap();
+    writeln!(html, "<h1>Relatório de Comissões</h1>").unwrap();
+
+    let mut grand_installment_total = Decimal::zero();
+    let mut grand_commission_total = Decimal::zero();
+
+    for month in ordered_months {
+        let commissions = commissions_by_month.get(month).unwrap();
+        let (installment_total, commission_total) = month_totals(commissions);
+        grand_installment_total += installment_total;
+        grand_commission_total += commission_total;
+
+        writeln!(html, "<section><h2>{}</h2>", escape_html(month)).unwrap();
+        write!(html, "<table><thead><tr><th>Emissão</th><th>Nr. NF</th><th>Cliente</th>\
+<th>Vlr. Parcela</th><th>Vlr. Comissão</th></tr></thead><tbody>").unwrap();
+
+        for commission in commissions {
+            write!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                   commission.emission_date.format("%m/%d/%Y"), commission.number,
+                   escape_html(&commission.client), commission.installment_value, commission.commission_value).unwrap();
+        }
+
+        writeln!(html, "</tbody><tfoot><tr><th colspan=\"3\">Total do mês</th><th>{}</th><th>{}</th></tr></tfoot></table></section>",
+                 installment_total, commission_total).unwrap();
+    }
+
+    writeln!(html, "<section class=\"grand-total\"><h2>Total geral</h2>\
+<p>Parcelas: {} &mdash; Comissões: {}</p></section>", grand_installment_total, grand_commission_total).unwrap();
+    writeln!(html, "</body></html>").unwrap();
+
+    fs::write(output_path, html)
+        .unwrap_or_else(|cause| panic!("could not write {}: {}", output_path, cause));
+}
+
+fn write_qif_export(output_path: &str, ordered_months: &[String], commissions_by_month: &HashMap<String, Vec<CommissionedInvoice>>) {
+    ensure_file_is_created(output_path);
+
+    let mut contents = String::new();
+    for month in ordered_months {
+        for commission in commissions_by_month.get(month).unwrap() {
+            writeln!(contents, "D{}", commission.emission_date.format("%m/%d/%Y")).unwrap();
+            writeln!(contents, "T{}", commission.commission_value).unwrap();
+            writeln!(contents, "M{} - NF {}", commission.client, commission.number).unwrap();
+            writeln!(contents, "^").unwrap();
+        }
+    }
+
+    fs::write(output_path, contents)
+        .unwrap_or_else(|cause| panic!("could not write {}: {}", output_path, cause));
+}
+
+fn write_ledger_export(output_path: &str, ordered_months: &[String], commissions_by_month: &HashMap<String, Vec<CommissionedInvoice>>) {
+    ensure_file_is_created(output_path);
+
+    let mut contents = String::new();
+    for month in ordered_months {
+        for commission in commissions_by_month.get(month).unwrap() {
+            writeln!(contents, "{} {} - NF {}",
+                     commission.emission_date.format("%Y/%m/%d"), commission.client, commission.number).unwrap();
+            writeln!(contents, "    Comissões a Receber        {}", commission.commission_value).unwrap();
+            writeln!(contents, "    Ativo:Comissões            -{}", commission.commission_value).unwrap();
+            writeln!(contents).unwrap();
+        }
+    }
+
+    fs::write(output_path, contents)
+        .unwrap_or_else(|cause| panic!("could not write {}: {}", output_path, cause));
+}